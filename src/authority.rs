@@ -0,0 +1,171 @@
+use crate::ffi::{self, KeyCtlOperation};
+use crate::utils::Vec;
+use crate::{Key, KeyError, KeyRing, KeyRingIdentifier, KeySerialId};
+
+/// Where a key instantiated (or negated/rejected) in response to a
+/// request-key upcall should be attached.
+///
+/// This mirrors the kernel's link-to-keyring rules for `KEYCTL_INSTANTIATE`
+/// and friends: a concrete keyring attaches the key there directly, while
+/// one of the special `KEY_SPEC_*` identifiers attaches the key to whichever
+/// keyring the original `request_key` call named as its destination.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Destination {
+    KeyRing(KeyRing),
+    Special(KeyRingIdentifier),
+}
+
+impl Destination {
+    fn as_raw(&self) -> libc::c_long {
+        match self {
+            Destination::KeyRing(ring) => ring.raw_id().as_raw_id() as libc::c_long,
+            Destination::Special(id) => *id as libc::c_long,
+        }
+    }
+}
+
+impl From<KeyRing> for Destination {
+    fn from(ring: KeyRing) -> Self {
+        Destination::KeyRing(ring)
+    }
+}
+
+impl From<KeyRingIdentifier> for Destination {
+    fn from(id: KeyRingIdentifier) -> Self {
+        Destination::Special(id)
+    }
+}
+
+/// The authority to instantiate, negate, or reject a key on behalf of
+/// `/sbin/request-key`.
+///
+/// When the kernel invokes a request-key handler, it passes the target
+/// key's ID and the serial of an authorisation key that embeds the
+/// original request, including any callout info. [Authority::assume]
+/// exchanges that authorisation key for the right to act on the target
+/// key; the handler then finishes the upcall with exactly one of
+/// [Authority::instantiate], [Authority::negate], or [Authority::reject].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Authority {
+    auth_key: KeySerialId,
+}
+
+impl Authority {
+    /// Assume the authority to instantiate the key associated with the
+    /// given authorisation key, as passed to a request-key handler on
+    /// invocation.
+    ///
+    /// Backed by `KEYCTL_ASSUME_AUTHORITY`.
+    pub fn assume(auth_key: KeySerialId) -> Result<Self, KeyError> {
+        _ = ffi::keyctl!(
+            KeyCtlOperation::AssumeAuthority,
+            auth_key.as_raw_id() as libc::c_ulong
+        )?;
+        Ok(Self { auth_key })
+    }
+
+    /// Relinquish the authority to instantiate any key. Equivalent to
+    /// assuming authority over key ID 0.
+    pub fn relinquish_authority() -> Result<(), KeyError> {
+        _ = ffi::keyctl!(KeyCtlOperation::AssumeAuthority, 0 as libc::c_ulong)?;
+        Ok(())
+    }
+
+    /// Read the callout info that the original caller passed to
+    /// `request_key`/`request_key_typed`, as embedded in the authorisation
+    /// key assumed by this [Authority].
+    pub fn callout_info(&self) -> Result<Vec<u8>, KeyError> {
+        Key::from_id(self.auth_key).read_to_vec()
+    }
+
+    /// Instantiate the target key with the given payload and attach it to
+    /// `dest`. See [Destination] for how `dest` is resolved.
+    pub fn instantiate<S: AsRef<[u8]> + ?Sized>(
+        &self,
+        key: KeySerialId,
+        payload: &S,
+        dest: Destination,
+    ) -> Result<(), KeyError> {
+        let payload = payload.as_ref();
+        _ = ffi::keyctl!(
+            KeyCtlOperation::Instantiate,
+            key.as_raw_id() as libc::c_ulong,
+            payload.as_ptr() as _,
+            payload.len() as _,
+            dest.as_raw() as _
+        )?;
+        Ok(())
+    }
+
+    /// Negatively instantiate the target key and attach it to `dest`: any
+    /// caller currently waiting on the key sees `ENOKEY` until `timeout`
+    /// seconds elapse, at which point the negative instantiation expires and
+    /// the key is removed. See [Destination] for how `dest` is resolved.
+    pub fn negate(
+        &self,
+        key: KeySerialId,
+        timeout_seconds: u32,
+        dest: Destination,
+    ) -> Result<(), KeyError> {
+        _ = ffi::keyctl!(
+            KeyCtlOperation::Negate,
+            key.as_raw_id() as libc::c_ulong,
+            timeout_seconds as libc::c_ulong,
+            dest.as_raw() as _
+        )?;
+        Ok(())
+    }
+
+    /// Like [Authority::negate], but callers waiting on the key see `error`
+    /// instead of `ENOKEY`.
+    pub fn reject(
+        &self,
+        key: KeySerialId,
+        timeout_seconds: u32,
+        error: i32,
+        dest: Destination,
+    ) -> Result<(), KeyError> {
+        _ = ffi::keyctl!(
+            KeyCtlOperation::Reject,
+            key.as_raw_id() as libc::c_ulong,
+            timeout_seconds as libc::c_ulong,
+            error as libc::c_ulong,
+            dest.as_raw() as _
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn test_authority_callout_info_round_trip() {
+        let callout = "Test Data from Authority Callout";
+
+        // Test that a keyring that should already exist is returned
+        let ring = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+
+        // The test expects that the key is instantiated by a helper invoked
+        // by /sbin/request-key that assumes authority over the auth key
+        // serial it is given via Authority::assume, reads the callout info
+        // back out with Authority::callout_info, and instantiates the
+        // target key with that same payload via Authority::instantiate,
+        // e.g. the following /etc/request-key.conf entry is known to work:
+        //
+        // create	user	test_authority_callout	*		/path/to/examples/authority --keyid %k --authkey %a
+        let key = ring
+            .request_key("test_authority_callout", Some(callout))
+            .unwrap();
+
+        // If the helper's Authority::callout_info() round-tripped the
+        // callout info correctly, the instantiated key's payload matches it.
+        let payload = key.read_to_vec().unwrap();
+        assert_eq!(callout.as_bytes(), &payload);
+
+        // Invalidate the key
+        key.invalidate().unwrap();
+    }
+}
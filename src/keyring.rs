@@ -1,7 +1,51 @@
 use crate::ffi::{self, KeyCtlOperation};
 use crate::utils::{CStr, CString, Vec};
 use crate::{Key, KeyError, KeyRingIdentifier, KeySerialId, KeyType, LinkNode, Links, Metadata};
-use core::convert::TryInto;
+use core::convert::{TryFrom, TryInto};
+
+/// The possible destinations for a key implicitly instantiated by a
+/// `request_key` upcall that named a special keyring rather than a concrete
+/// one, as set by [KeyRing::set_default_request_key_destination].
+///
+/// Mirrors the kernel's `KEY_REQKEY_DEFL_*` constants.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum DefaultKeyring {
+    NoChange = -1,
+    Default = 0,
+    Thread = 1,
+    Process = 2,
+    Session = 3,
+    User = 4,
+    UserSession = 5,
+    Group = 6,
+    Requestor = 7,
+}
+
+impl TryFrom<i32> for DefaultKeyring {
+    type Error = KeyError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(Self::NoChange),
+            0 => Ok(Self::Default),
+            1 => Ok(Self::Thread),
+            2 => Ok(Self::Process),
+            3 => Ok(Self::Session),
+            4 => Ok(Self::User),
+            5 => Ok(Self::UserSession),
+            6 => Ok(Self::Group),
+            7 => Ok(Self::Requestor),
+            _ => Err(KeyError::InvalidIdentifier),
+        }
+    }
+}
+
+/// Maximum keyring nesting depth enforced by the kernel (the
+/// `KEYRING_SEARCH_MAX_DEPTH` constant mentioned in [KeyRing::link_key]),
+/// mirrored here so [KeyRing::walk] can't recurse forever on a cyclic or
+/// misbehaving tree.
+const KEYRING_SEARCH_MAX_DEPTH: usize = 6;
 
 /// Interface to perform keyring operations. Used to locate, create,
 /// search, add, and link/unlink keys to & from keyrings.
@@ -16,6 +60,11 @@ impl KeyRing {
         Self { id }
     }
 
+    /// Obtain the raw serial ID of this keyring.
+    pub(crate) fn raw_id(&self) -> KeySerialId {
+        self.id
+    }
+
     /// Obtain a KeyRing from its special identifier.
     ///
     /// If the create argument is true, then this method will attempt
@@ -65,6 +114,53 @@ impl KeyRing {
         Ok(Self { id })
     }
 
+    /// Join, or create and join, a named (or anonymous) session keyring,
+    /// installing it as the caller's session keyring and returning it.
+    ///
+    /// If `name` is `None`, the kernel creates a new anonymous session
+    /// keyring and installs it unconditionally.
+    ///
+    /// If `name` is `Some`, the kernel looks for an existing session keyring
+    /// with that name that the caller has permission to join (search
+    /// permission on it); if one is found, the caller joins it and it
+    /// becomes the caller's session keyring. Otherwise a new keyring of that
+    /// name is created and installed, just as in the anonymous case.
+    ///
+    /// Unlike [KeyRing::from_special_id] with [KeyRingIdentifier::Session],
+    /// which can only resolve a session keyring that is already installed,
+    /// this is what lets a process set up (or attach to) an isolated
+    /// credential namespace for itself, the same way `keyctl session` does.
+    pub fn join_session<N: AsRef<str> + ?Sized>(name: Option<&N>) -> Result<Self, KeyError> {
+        let name = name
+            .map(|n| CString::new(n.as_ref()).or(Err(KeyError::InvalidDescription)))
+            .transpose()?;
+        let id: KeySerialId = ffi::keyctl!(
+            KeyCtlOperation::JoinSessionKeyring,
+            name.as_ref()
+                .map_or(core::ptr::null(), |n| n.as_ptr()) as _
+        )?
+        .try_into()
+        .or(Err(KeyError::InvalidIdentifier))?;
+        Ok(Self { id })
+    }
+
+    /// Set the default destination keyring for a key implicitly instantiated
+    /// by a `request_key` upcall that named one of the special `KEY_SPEC_*`
+    /// keyrings rather than a concrete one.
+    ///
+    /// Returns the previous setting, so callers can save and restore it
+    /// around a scoped operation (e.g. a daemon routing auto-instantiated
+    /// keys into its process keyring without naming it in every
+    /// `request_key` call).
+    ///
+    /// Backed by `KEYCTL_SET_REQKEY_KEYRING`.
+    pub fn set_default_request_key_destination(
+        dest: DefaultKeyring,
+    ) -> Result<DefaultKeyring, KeyError> {
+        let previous = ffi::keyctl!(KeyCtlOperation::SetReqKeyKeyring, dest as libc::c_long as _)?;
+        DefaultKeyring::try_from(previous as i32)
+    }
+
     /// Obtain information describing the attributes of this keyring.
     ///
     /// The keyring must grant the caller view permission.
@@ -85,9 +181,51 @@ impl KeyRing {
         &self,
         description: &D,
         secret: &S,
+    ) -> Result<Key, KeyError> {
+        self.add_key_typed(KeyType::User, description, secret)
+    }
+
+    /// Creates or updates a key of the given description and BigKey type,
+    /// instantiates it with the payload of length plen, attaches it to the
+    /// keyring.
+    ///
+    /// The `big_key` key type, unlike `user`, is not subject to the per-user
+    /// key quota and is not pinned in non-swappable kernel memory: payloads
+    /// above roughly 1 MiB are transparently offloaded by the kernel to a
+    /// tmpfs-backed shmem file (smaller payloads are kept inline, same as
+    /// `user` keys). This makes it suitable for large secrets, such as
+    /// Kerberos ticket caches, that would otherwise be too big for
+    /// [KeyRing::add_key].
+    ///
+    /// Behaves the same as [KeyRing::add_key] in all other respects.
+    pub fn add_big_key<D: AsRef<str> + ?Sized, S: AsRef<[u8]> + ?Sized>(
+        &self,
+        description: &D,
+        secret: &S,
+    ) -> Result<Key, KeyError> {
+        self.add_key_typed(KeyType::BigKey, description, secret)
+    }
+
+    /// Creates or updates a key of the given type and description,
+    /// instantiates it with the payload of length plen, attaches it to the
+    /// keyring.
+    ///
+    /// This is the type-generic form of [KeyRing::add_key] / [KeyRing::add_big_key].
+    /// It also unlocks key types whose payload follows the kernel's own
+    /// command grammar rather than being an opaque secret, e.g. `encrypted`
+    /// (`new user:<master-key-desc> 32` to mint a key sealed under a master
+    /// key, or `load <hex-blob>` to restore one), `trusted`, `keyring`, and
+    /// write-only `logon` credentials.
+    ///
+    /// Behaves the same as [KeyRing::add_key] in all other respects.
+    pub fn add_key_typed<D: AsRef<str> + ?Sized, S: AsRef<[u8]> + ?Sized>(
+        &self,
+        key_type: KeyType,
+        description: &D,
+        secret: &S,
     ) -> Result<Key, KeyError> {
         let id = ffi::add_key(
-            KeyType::User,
+            key_type,
             self.id.as_raw_id() as libc::c_ulong,
             description.as_ref(),
             Some(secret.as_ref()),
@@ -112,9 +250,35 @@ impl KeyRing {
         &self,
         description: &D,
         callout: Option<&C>,
+    ) -> Result<Key, KeyError> {
+        self.request_key_typed(KeyType::User, description, callout)
+    }
+
+    /// Same as [KeyRing::request_key], but looks up (or triggers instantiation
+    /// of) a key of the BigKey type rather than User. See [KeyRing::add_big_key]
+    /// for why you might want a `big_key`-typed key over a `user` one.
+    pub fn request_big_key<D: AsRef<str> + ?Sized, C: AsRef<str> + ?Sized>(
+        &self,
+        description: &D,
+        callout: Option<&C>,
+    ) -> Result<Key, KeyError> {
+        self.request_key_typed(KeyType::BigKey, description, callout)
+    }
+
+    /// Attempts to find a key of the given type with a description that
+    /// matches the specified description, optionally creating it via a
+    /// `/sbin/request-key` upcall. This is the type-generic form of
+    /// [KeyRing::request_key] / [KeyRing::request_big_key].
+    ///
+    /// Behaves the same as [KeyRing::request_key] in all other respects.
+    pub fn request_key_typed<D: AsRef<str> + ?Sized, C: AsRef<str> + ?Sized>(
+        &self,
+        key_type: KeyType,
+        description: &D,
+        callout: Option<&C>,
     ) -> Result<Key, KeyError> {
         let id = ffi::request_key(
-            KeyType::User,
+            key_type,
             self.id.as_raw_id() as libc::c_ulong,
             description.as_ref(),
             callout.map(|c| c.as_ref()),
@@ -134,6 +298,16 @@ impl KeyRing {
     ///
     /// If the key is found, its ID is returned as the function result.
     pub fn search<D: AsRef<str> + ?Sized>(&self, description: &D) -> Result<Key, KeyError> {
+        self.search_typed(KeyType::User, description)
+    }
+
+    /// Same as [KeyRing::search], but searches for a key of the given type
+    /// rather than assuming User.
+    pub fn search_typed<D: AsRef<str> + ?Sized>(
+        &self,
+        key_type: KeyType,
+        description: &D,
+    ) -> Result<Key, KeyError> {
         // The provided description must be properly null terminated for the kernel
         let description =
             CString::new(description.as_ref()).or(Err(KeyError::InvalidDescription))?;
@@ -142,7 +316,7 @@ impl KeyRing {
         let id: KeySerialId = ffi::keyctl!(
             KeyCtlOperation::Search,
             self.id.as_raw_id() as libc::c_ulong,
-            Into::<&'static CStr>::into(KeyType::User).as_ptr() as _,
+            Into::<&'static CStr>::into(key_type).as_ptr() as _,
             description.as_ptr() as _,
             0
         )?
@@ -184,6 +358,56 @@ impl KeyRing {
             .collect())
     }
 
+    /// Recursively walk the tree of keys and keyrings reachable from this
+    /// keyring, breadth-first, the same way the kernel's own search does.
+    ///
+    /// Every direct and indirect child keyring is descended into, in
+    /// breadth-first order, up to a nesting depth of
+    /// [KEYRING_SEARCH_MAX_DEPTH], the same limit the kernel itself enforces
+    /// when searching. A keyring the caller lacks search permission on is
+    /// pruned rather than failing the whole walk, and the fixed depth limit
+    /// means a cycle (however the kernel otherwise prevents one) can't spin
+    /// this traversal forever.
+    ///
+    /// `max_per_ring` bounds how many links are read from any single
+    /// keyring, same as the `max` argument to [KeyRing::get_links].
+    ///
+    /// Each entry is a `(depth, LinkNode)` pair, with `depth` counting this
+    /// keyring's direct links as depth 0. This gives callers a way to
+    /// enumerate an entire credential hierarchy (e.g. to audit or
+    /// garbage-collect keys reachable from a session keyring), which a
+    /// single [KeyRing::get_links] call cannot.
+    pub fn walk(&self, max_per_ring: usize) -> Result<Vec<(usize, LinkNode)>, KeyError> {
+        let mut results = Vec::new();
+        let mut frontier = Vec::new();
+        frontier.push(*self);
+
+        let mut depth = 0;
+        while !frontier.is_empty() && depth < KEYRING_SEARCH_MAX_DEPTH {
+            let mut next_frontier = Vec::new();
+            for ring in frontier.iter() {
+                // Prune only a keyring we lack search permission on; any
+                // other failure is a genuine error and should propagate
+                // rather than be silently reported as an empty/partial walk.
+                let links = match ring.get_links(max_per_ring) {
+                    Ok(links) => links,
+                    Err(KeyError::AccessDenied) => continue,
+                    Err(e) => return Err(e),
+                };
+                for node in links.iter() {
+                    if let Ok(child) = node.as_keyring() {
+                        next_frontier.push(child);
+                    }
+                    results.push((depth, node.clone()));
+                }
+            }
+            frontier = next_frontier;
+            depth += 1;
+        }
+
+        Ok(results)
+    }
+
     /// Create a link from this keyring to a key.
     ///
     /// If a key with the same type and description is already linked in the keyring,
@@ -313,6 +537,24 @@ mod test {
         assert!(ring.id.as_raw_id() > 0);
     }
 
+    #[test]
+    fn test_join_session() {
+        // Joining with no name creates a fresh anonymous session keyring
+        // and installs it as the caller's session keyring.
+        let anon = KeyRing::join_session::<str>(None).unwrap();
+        let installed = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+        assert_eq!(anon, installed);
+
+        // Joining with a name creates (and installs) a named session keyring.
+        let named = KeyRing::join_session(Some("test_join_session")).unwrap();
+        let installed = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+        assert_eq!(named, installed);
+
+        // Joining the same name again re-joins the existing keyring.
+        let rejoined = KeyRing::join_session(Some("test_join_session")).unwrap();
+        assert_eq!(named, rejoined);
+    }
+
     #[test]
     fn test_get_persistent() {
         // Test that a keyring that should already exist is returned
@@ -323,6 +565,17 @@ mod test {
         assert_ne!(user_ring, user_perm_ring);
     }
 
+    #[test]
+    fn test_set_default_request_key_destination() {
+        // Save the current setting, change it, and confirm the prior value
+        // is handed back on restore.
+        let previous = KeyRing::set_default_request_key_destination(DefaultKeyring::Thread)
+            .unwrap();
+
+        let restored = KeyRing::set_default_request_key_destination(previous).unwrap();
+        assert_eq!(restored, DefaultKeyring::Thread);
+    }
+
     #[test]
     fn test_metadata() {
         // Test that a keyring that normally doesn't exist by default is
@@ -407,6 +660,54 @@ mod test {
         key.invalidate().unwrap();
     }
 
+    #[test]
+    fn test_add_big_key_round_trip() {
+        // Test that a keyring that should already exist is returned
+        let ring = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+
+        // A payload well past the ~1 MiB inline/offload threshold, to exercise
+        // the tmpfs-backed shmem path as well as the inline one.
+        let payload = vec![0x5au8; 4 * 1024 * 1024];
+
+        let key = ring.add_big_key("test_big_key", &payload).unwrap();
+
+        // The full payload must round-trip regardless of how the kernel chose
+        // to store it internally.
+        let result = key.read_to_vec().unwrap();
+        assert_eq!(payload, result);
+
+        // Invalidate the key
+        key.invalidate().unwrap();
+    }
+
+    #[test]
+    fn test_typed_add_request_search() {
+        // Test that a keyring that should already exist is returned
+        let ring = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+        let key = ring
+            .add_key_typed(KeyType::User, "test_typed", b"data")
+            .unwrap();
+
+        // Ensure we have search permission on the key
+        let perms = KeyPermissionsBuilder::builder()
+            .posessor(Permission::ALL)
+            .user(Permission::ALL)
+            .build();
+        key.set_perms(perms).unwrap();
+
+        // Searching and requesting by type should find the same key
+        let result = ring.search_typed(KeyType::User, "test_typed").unwrap();
+        assert_eq!(key.get_id(), result.get_id());
+
+        let result = ring
+            .request_key_typed(KeyType::User, "test_typed", None::<&str>)
+            .unwrap();
+        assert_eq!(key.get_id(), result.get_id());
+
+        // Invalidate the key
+        key.invalidate().unwrap();
+    }
+
     #[test]
     fn test_search_non_existing_key() {
         // Test that a keyring that normally doesn't exist by default is
@@ -496,6 +797,32 @@ mod test {
         assert!(sess.id.as_raw_id() > 0);
     }
 
+    #[test]
+    fn test_walk() {
+        // Build a small two-level tree: session -> thread -> key
+        let sess = KeyRing::from_special_id(KeyRingIdentifier::Session, false).unwrap();
+        let thread = KeyRing::from_special_id(KeyRingIdentifier::Thread, true).unwrap();
+        sess.link_keyring(thread).unwrap();
+        let key = thread.add_key("test_walk", b"data").unwrap();
+
+        let nodes = sess.walk(200).unwrap();
+
+        // The thread keyring is a direct link (depth 0)...
+        assert!(nodes
+            .iter()
+            .any(|(depth, node)| *depth == 0 && node.as_keyring().map_or(false, |k| k == thread)));
+
+        // ...and the key nested inside it is found one level deeper (depth 1).
+        assert!(nodes.iter().any(|(depth, node)| *depth == 1
+            && node
+                .as_key()
+                .map_or(false, |k| k.get_id() == key.get_id())));
+
+        // Clean up
+        key.invalidate().unwrap();
+        sess.unlink_keyring(thread).unwrap();
+    }
+
     #[test]
     fn test_get_linked_items() {
         // Test that a keyring that should already exist is returned
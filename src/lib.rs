@@ -0,0 +1,5 @@
+mod authority;
+mod keyring;
+
+pub use authority::{Authority, Destination};
+pub use keyring::{DefaultKeyring, KeyRing};